@@ -0,0 +1,41 @@
+mod server;
+mod sled_store;
+
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+/// Command-line configuration for the driftdb-server binary.
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+pub struct Opts {
+    /// Address to bind the HTTP server to.
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: IpAddr,
+
+    /// Port to bind the HTTP server to.
+    #[arg(long, default_value_t = 8787)]
+    pub port: u16,
+
+    /// Directory for the embedded sled database. When omitted the server
+    /// keeps its state purely in memory and loses it on restart.
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Seconds between WebSocket heartbeat pings.
+    #[arg(long, default_value_t = 30)]
+    pub heartbeat_secs: u64,
+
+    /// Seconds of client inactivity before a connection is dropped.
+    #[arg(long, default_value_t = 90)]
+    pub client_timeout_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let opts = Opts::parse();
+    server::run_server(&opts).await
+}
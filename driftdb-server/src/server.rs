@@ -3,14 +3,25 @@ use axum::{
     body::BoxBody,
     error_handling::HandleError,
     extract::{ws::WebSocket, Query, State, WebSocketUpgrade},
-    response::Response,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
     routing::get,
     Router,
 };
-use driftdb::{Database, MessageFromDatabase, MessageToDatabase};
+use driftdb::{Database, Key, MessageFromDatabase, MessageToDatabase, SequenceNumber};
+use futures::Stream;
 use hyper::{Method, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap, convert::Infallible, net::SocketAddr, path::Path, sync::Arc,
+    time::Duration,
+};
+use tokio::time::Instant;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+use crate::sled_store::SledStore;
 use tower_http::{
     cors::{AllowOrigin, CorsLayer},
     services::ServeDir,
@@ -20,61 +31,272 @@ use tracing::Level;
 
 use crate::Opts;
 
+/// Wire encoding negotiated via the `?encoding=` query param on `/ws`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Encoding {
+    /// JSON text frames (the default). One message per `Message::Text` frame.
+    #[default]
+    Json,
+
+    /// MessagePack binary frames via `rmp-serde`. Messages larger than
+    /// [`CHUNK_SIZE`] are split across several `Message::Binary` frames, each
+    /// prefixed with a [`FRAME_HEADER_LEN`]-byte header, and reassembled on the
+    /// far side before deserializing.
+    Msgpack,
+}
+
+/// Maximum payload bytes carried in a single MessagePack binary frame. Larger
+/// serialized messages are fragmented into this many bytes per frame so a big
+/// `Push` value never trips a WebSocket frame-size limit.
+const CHUNK_SIZE: usize = 60 * 1024;
+
+/// Fixed header prepended to every MessagePack frame: a frame id shared by all
+/// fragments of one message, followed by this fragment's index and the total
+/// fragment count (all little-endian).
+const FRAME_HEADER_LEN: usize = 4 + 2 + 2;
+
+/// Maximum number of chunked messages a single connection may have partially
+/// received at once. Bounds the memory a peer can pin by opening many
+/// multi-fragment messages and never completing any of them.
+const MAX_PARTIAL_FRAMES: usize = 64;
+
+/// How long a partially-received chunked message may sit incomplete before it
+/// is dropped, swept alongside the heartbeat check.
+const PARTIAL_FRAME_TIMEOUT: Duration = Duration::from_secs(60);
+
 struct TypedWebSocket<Inbound: DeserializeOwned, Outbound: Serialize> {
     socket: WebSocket,
+    encoding: Encoding,
+    /// When the last frame of any kind arrived from the peer, used to reap
+    /// idle connections.
+    last_activity: Instant,
+    /// Counter handing out a fresh frame id for each outbound chunked message.
+    next_frame_id: u32,
+    /// Reassembly state for incoming chunked binary messages. Kept as its own
+    /// type, independent of the socket, so it can be unit tested directly.
+    reassembler: FrameReassembler,
     _ph_inbound: std::marker::PhantomData<Inbound>,
     _ph_outbound: std::marker::PhantomData<Outbound>,
 }
 
+/// Reassembles chunked MessagePack binary frames (see [`TypedWebSocket::send`]
+/// for how they are split) back into whole messages.
+#[derive(Default)]
+struct FrameReassembler {
+    /// Partially-received binary messages, keyed by frame id. Each entry holds
+    /// the fragments collected so far; we only deserialize once all arrive.
+    /// Bounded by [`MAX_PARTIAL_FRAMES`] and swept of stale entries older than
+    /// [`PARTIAL_FRAME_TIMEOUT`].
+    partial: HashMap<u32, PartialFrame>,
+}
+
+/// An in-flight reassembly of a chunked MessagePack message.
+struct PartialFrame {
+    total: u16,
+    fragments: Vec<Option<Vec<u8>>>,
+    received: u16,
+    /// When the first fragment of this frame arrived, used to evict an
+    /// abandoned reassembly.
+    started: Instant,
+}
+
+impl FrameReassembler {
+    /// Feed one binary fragment into the reassembly buffer, returning the full
+    /// payload once every fragment of its frame has arrived.
+    fn reassemble(&mut self, frame: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        if frame.len() < FRAME_HEADER_LEN {
+            return Err(anyhow::anyhow!("Binary frame shorter than header."));
+        }
+
+        let frame_id = u32::from_le_bytes(frame[0..4].try_into().unwrap());
+        let part = u16::from_le_bytes(frame[4..6].try_into().unwrap());
+        let total = u16::from_le_bytes(frame[6..8].try_into().unwrap());
+        let payload = frame[FRAME_HEADER_LEN..].to_vec();
+
+        if total == 0 || part >= total {
+            return Err(anyhow::anyhow!("Invalid fragment index {part}/{total}."));
+        }
+
+        // Fast path: a single-fragment message needs no buffering.
+        if total == 1 {
+            return Ok(Some(payload));
+        }
+
+        let at_capacity = self.partial.len() >= MAX_PARTIAL_FRAMES;
+
+        let entry = match self.partial.entry(frame_id) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                if at_capacity {
+                    return Err(anyhow::anyhow!(
+                        "Too many in-flight chunked messages ({MAX_PARTIAL_FRAMES})."
+                    ));
+                }
+                entry.insert(PartialFrame {
+                    total,
+                    fragments: (0..total).map(|_| None).collect(),
+                    received: 0,
+                    started: Instant::now(),
+                })
+            }
+        };
+
+        if entry.total != total {
+            return Err(anyhow::anyhow!("Fragment count changed mid-frame."));
+        }
+
+        if entry.fragments[part as usize].replace(payload).is_none() {
+            entry.received += 1;
+        }
+
+        if entry.received == entry.total {
+            let entry = self.partial.remove(&frame_id).unwrap();
+            let bytes = entry
+                .fragments
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect::<Vec<u8>>();
+            return Ok(Some(bytes));
+        }
+
+        Ok(None)
+    }
+
+    /// Drop any chunked message that has sat incomplete longer than
+    /// [`PARTIAL_FRAME_TIMEOUT`], freeing the memory an abandoned reassembly
+    /// would otherwise hold for the life of the connection.
+    fn evict_stale_partials(&mut self) {
+        self.partial
+            .retain(|_, frame| frame.started.elapsed() < PARTIAL_FRAME_TIMEOUT);
+    }
+
+    /// How many chunked messages are currently mid-reassembly.
+    #[cfg(test)]
+    fn pending_count(&self) -> usize {
+        self.partial.len()
+    }
+}
+
 impl<Inbound: DeserializeOwned, Outbound: Serialize> TypedWebSocket<Inbound, Outbound> {
     pub fn new(socket: WebSocket) -> Self {
+        Self::with_encoding(socket, Encoding::Json)
+    }
+
+    pub fn with_encoding(socket: WebSocket, encoding: Encoding) -> Self {
         Self {
             socket,
+            encoding,
+            last_activity: Instant::now(),
+            next_frame_id: 0,
+            reassembler: FrameReassembler::default(),
             _ph_inbound: std::marker::PhantomData,
             _ph_outbound: std::marker::PhantomData,
         }
     }
 
+    /// How long since any frame (including `Pong`) was received from the peer.
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+
+    /// Send a heartbeat `Ping` to the peer.
+    pub async fn send_ping(&mut self) -> Result<()> {
+        self.socket
+            .send(axum::extract::ws::Message::Ping(vec![]))
+            .await?;
+        Ok(())
+    }
+
     pub async fn recv(&mut self) -> Result<Option<Inbound>> {
-        let msg = self.socket.recv().await.transpose()?;
         loop {
-            match &msg {
-                Some(msg) => match msg {
-                    axum::extract::ws::Message::Close(_) => {
-                        return Ok(None);
-                    }
-                    axum::extract::ws::Message::Ping(_) => {
-                        self.socket
-                            .send(axum::extract::ws::Message::Pong(vec![]))
-                            .await?;
-                    }
-                    axum::extract::ws::Message::Pong(_) => {}
-                    axum::extract::ws::Message::Binary(_) => {
+            let msg = self.socket.recv().await.transpose()?;
+            self.last_activity = Instant::now();
+
+            match msg {
+                Some(axum::extract::ws::Message::Close(_)) | None => {
+                    return Ok(None);
+                }
+                Some(axum::extract::ws::Message::Ping(_)) => {
+                    self.socket
+                        .send(axum::extract::ws::Message::Pong(vec![]))
+                        .await?;
+                }
+                Some(axum::extract::ws::Message::Pong(_)) => {}
+                Some(axum::extract::ws::Message::Binary(frame)) => {
+                    if self.encoding != Encoding::Msgpack {
                         return Err(anyhow::anyhow!("Binary messages are not supported."));
                     }
-                    axum::extract::ws::Message::Text(msg) => {
-                        let msg = serde_json::from_str(&msg)?;
+
+                    if let Some(bytes) = self.reassembler.reassemble(frame)? {
+                        let msg = rmp_serde::from_slice(&bytes)?;
                         return Ok(Some(msg));
                     }
-                },
-                None => return Ok(None),
+                    // Still waiting on further fragments of this message.
+                }
+                Some(axum::extract::ws::Message::Text(msg)) => {
+                    let msg = serde_json::from_str(&msg)?;
+                    return Ok(Some(msg));
+                }
             }
         }
     }
 
+    /// Drop any chunked message that has sat incomplete longer than
+    /// [`PARTIAL_FRAME_TIMEOUT`], freeing the memory an abandoned reassembly
+    /// would otherwise hold for the life of the connection.
+    fn evict_stale_partials(&mut self) {
+        self.reassembler.evict_stale_partials();
+    }
+
     pub async fn send(&mut self, msg: Outbound) -> Result<()> {
-        let msg = serde_json::to_string(&msg)?;
-        self.socket
-            .send(axum::extract::ws::Message::Text(msg))
-            .await?;
+        match self.encoding {
+            Encoding::Json => {
+                let msg = serde_json::to_string(&msg)?;
+                self.socket
+                    .send(axum::extract::ws::Message::Text(msg))
+                    .await?;
+            }
+            Encoding::Msgpack => {
+                let bytes = rmp_serde::to_vec_named(&msg)?;
+                let frame_id = self.next_frame_id;
+                self.next_frame_id = self.next_frame_id.wrapping_add(1);
+
+                let total = bytes.len().div_ceil(CHUNK_SIZE).max(1);
+                for (part, chunk) in bytes.chunks(CHUNK_SIZE).enumerate() {
+                    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + chunk.len());
+                    frame.extend_from_slice(&frame_id.to_le_bytes());
+                    frame.extend_from_slice(&(part as u16).to_le_bytes());
+                    frame.extend_from_slice(&(total as u16).to_le_bytes());
+                    frame.extend_from_slice(chunk);
+                    self.socket
+                        .send(axum::extract::ws::Message::Binary(frame))
+                        .await?;
+                }
+            }
+        }
         Ok(())
     }
 }
 
-async fn handle_socket(socket: WebSocket, database: Arc<Database>, debug: bool) {
+/// Whether a connection idle for `idle_for` should be dropped given
+/// `client_timeout`, checked once per heartbeat tick.
+fn is_idle_timed_out(idle_for: Duration, client_timeout: Duration) -> bool {
+    idle_for > client_timeout
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    database: Arc<Database>,
+    debug: bool,
+    encoding: Encoding,
+    heartbeat_interval: Duration,
+    client_timeout: Duration,
+) {
     let (sender, mut receiver) = tokio::sync::mpsc::channel(32);
     let mut socket: TypedWebSocket<MessageToDatabase, MessageFromDatabase> =
-        TypedWebSocket::new(socket);
+        TypedWebSocket::with_encoding(socket, encoding);
 
     let callback = move |message: &MessageFromDatabase| {
         let result = sender.try_send(message.clone());
@@ -93,8 +315,36 @@ async fn handle_socket(socket: WebSocket, database: Arc<Database>, debug: bool)
         database.connect(callback)
     };
 
+    // This loop does no ack bookkeeping of its own: it only forwards what
+    // `receiver` yields. Nothing in driftdb-server's replica-callback path
+    // (see `sled_store.rs::into_database`) constructs a
+    // `MessageFromDatabase::Ack`, so no Ack reaches this socket and
+    // `Durability::Hard` is equivalent to `Soft` on this backend.
+    // `Ack` carries the pushed `seq`, which a client can match against the
+    // `Push` broadcast of its own write.
+    let mut heartbeat = tokio::time::interval(heartbeat_interval);
+    // The first tick fires immediately; skip it so we don't ping on connect.
+    heartbeat.tick().await;
+
     loop {
         tokio::select! {
+            _ = heartbeat.tick() => {
+                // Sweep abandoned chunked-message reassemblies alongside the
+                // idle check below.
+                socket.evict_stale_partials();
+
+                // Drop peers that have gone silent (e.g. a half-open socket
+                // whose TCP FIN never arrived) so their subscription is freed.
+                if is_idle_timed_out(socket.idle_for(), client_timeout) {
+                    tracing::warn!(?client_timeout, "No traffic from client within timeout; closing connection.");
+                    break;
+                }
+
+                if let Err(e) = socket.send_ping().await {
+                    tracing::error!(?e, "Failed to send heartbeat ping; closing connection.");
+                    break;
+                }
+            }
             msg = receiver.recv() => {
                 // We've received a message from the database; forward it to user.
 
@@ -134,31 +384,143 @@ async fn handle_socket(socket: WebSocket, database: Arc<Database>, debug: bool)
     }
 }
 
+#[derive(Clone)]
+struct AppState {
+    database: Arc<Database>,
+    heartbeat_interval: Duration,
+    client_timeout: Duration,
+}
+
 #[derive(Deserialize)]
 struct ConnectionQuery {
     #[serde(default)]
     debug: bool,
+
+    #[serde(default)]
+    encoding: Encoding,
 }
 
 async fn connection(
     ws: WebSocketUpgrade,
-    State(database): State<Arc<Database>>,
+    State(state): State<AppState>,
     Query(query): Query<ConnectionQuery>,
 ) -> Response<BoxBody> {
-    ws.on_upgrade(move |socket| handle_socket(socket, database, query.debug))
+    ws.on_upgrade(move |socket| {
+        handle_socket(
+            socket,
+            state.database,
+            query.debug,
+            query.encoding,
+            state.heartbeat_interval,
+            state.client_timeout,
+        )
+    })
 }
 
-pub fn api_routes() -> Result<Router> {
+#[derive(Deserialize)]
+struct SseQuery {
+    key: Key,
+
+    #[serde(default)]
+    seq: SequenceNumber,
+}
+
+/// Read-only Server-Sent Events subscription for a single key.
+///
+/// Replays the `Init` snapshot from `seq` as the first event, then streams each
+/// `Push` for the subscribed key. Unlike `/ws` this needs no upgrade, so
+/// browsers, `curl`, and proxies that choke on long-lived WebSocket upgrades
+/// can still consume a stream read-only.
+async fn sse_connection(
+    State(state): State<AppState>,
+    Query(query): Query<SseQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let database = state.database;
+    let SseQuery { key, seq } = query;
+    let (sender, receiver) = tokio::sync::mpsc::channel(32);
+
+    let filter_key = key.clone();
+    let callback = move |message: &MessageFromDatabase| {
+        if is_relevant_to_sse_subscriber(message, &filter_key) {
+            if let Err(err) = sender.try_send(message.clone()) {
+                tracing::error!(?err, "Failed to forward message to SSE subscriber.");
+            }
+        }
+    };
+
+    let conn = database.connect(callback);
+
+    // Prime the stream with the snapshot at `seq`.
+    if let Err(e) = conn.send_message(&MessageToDatabase::Dump { seq }) {
+        tracing::error!(?e, "Failed to request dump for SSE subscriber.");
+    }
+
+    let stream = ReceiverStream::new(receiver).map(move |message| {
+        // Hold the connection for as long as the stream is alive so the
+        // subscription's callback is not dropped early.
+        let _conn = &conn;
+
+        let event = Event::default()
+            .event(sse_event_name(&message))
+            .json_data(&message)
+            .expect("MessageFromDatabase always serializes to JSON.");
+
+        Ok(event)
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Whether `message` is relevant to a single-key SSE subscriber: the initial
+/// snapshot is always forwarded, a `Push` only if it is for the subscribed
+/// key, and everything else on the connection is dropped.
+fn is_relevant_to_sse_subscriber(message: &MessageFromDatabase, filter_key: &Key) -> bool {
+    match message {
+        MessageFromDatabase::Init { .. } => true,
+        MessageFromDatabase::Push { key, .. } => key == filter_key,
+        _ => false,
+    }
+}
+
+/// The SSE `event:` name a client should dispatch on for `message`.
+fn sse_event_name(message: &MessageFromDatabase) -> &'static str {
+    match message {
+        MessageFromDatabase::Init { .. } => "init",
+        MessageFromDatabase::Push { .. } => "push",
+        MessageFromDatabase::Ack { .. } => "ack",
+        MessageFromDatabase::ScanResult { .. } => "scan_result",
+        MessageFromDatabase::Error { .. } => "error",
+        MessageFromDatabase::StreamSize { .. } => "stream_size",
+    }
+}
+
+pub fn api_routes(
+    data_dir: Option<&Path>,
+    heartbeat_secs: u64,
+    client_timeout_secs: u64,
+) -> Result<Router> {
     let cors = CorsLayer::new()
         .allow_methods([Method::GET])
         .allow_origin(AllowOrigin::any());
 
-    let database = Database::new();
+    // With `--data-dir` the server persists to an embedded sled database and
+    // recovers its state on restart; otherwise it stays purely in-memory.
+    let database = match data_dir {
+        Some(data_dir) => SledStore::open(data_dir)?.into_database()?,
+        None => Database::new(),
+    };
+
+    let state = AppState {
+        database: Arc::new(database),
+        heartbeat_interval: Duration::from_secs(heartbeat_secs),
+        client_timeout: Duration::from_secs(client_timeout_secs),
+    };
 
     Ok(Router::new()
         .route("/ws", get(connection))
+        .route("/sse", get(sse_connection))
         .layer(cors)
-        .with_state(Arc::new(database)))
+        .with_state(state))
 }
 
 async fn handle_servedir_error(err: std::io::Error) -> (StatusCode, String) {
@@ -175,7 +537,14 @@ pub async fn run_server(opts: &Opts) -> anyhow::Result<()> {
         .on_response(DefaultOnResponse::new().level(Level::INFO));
 
     let app = Router::new()
-        .nest("/api/", api_routes()?)
+        .nest(
+            "/api/",
+            api_routes(
+                opts.data_dir.as_deref(),
+                opts.heartbeat_secs,
+                opts.client_timeout_secs,
+            )?,
+        )
         .nest_service(
             "/",
             HandleError::new(ServeDir::new("../driftdb-ui/build"), handle_servedir_error),
@@ -191,3 +560,158 @@ pub async fn run_server(opts: &Opts) -> anyhow::Result<()> {
 
     Err(anyhow::anyhow!("Server exited."))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use driftdb::types::SequenceValue;
+
+    fn push(key: &str) -> MessageFromDatabase {
+        MessageFromDatabase::Push {
+            key: Key::from(key),
+            value: SequenceValue {
+                value: serde_json::Value::Null,
+                seq: SequenceNumber(1),
+            },
+        }
+    }
+
+    #[test]
+    fn sse_subscriber_sees_init_and_own_key_pushes() {
+        let filter_key = Key::from("doc");
+
+        assert!(is_relevant_to_sse_subscriber(
+            &MessageFromDatabase::Init { data: vec![] },
+            &filter_key
+        ));
+        assert!(is_relevant_to_sse_subscriber(&push("doc"), &filter_key));
+    }
+
+    #[test]
+    fn sse_subscriber_ignores_other_keys_and_message_kinds() {
+        let filter_key = Key::from("doc");
+
+        assert!(!is_relevant_to_sse_subscriber(
+            &push("other-doc"),
+            &filter_key
+        ));
+        assert!(!is_relevant_to_sse_subscriber(
+            &MessageFromDatabase::Error {
+                message: "oops".to_string()
+            },
+            &filter_key
+        ));
+    }
+
+    #[test]
+    fn sse_event_names_match_message_variant() {
+        assert_eq!(sse_event_name(&MessageFromDatabase::Init { data: vec![] }), "init");
+        assert_eq!(sse_event_name(&push("doc")), "push");
+        assert_eq!(
+            sse_event_name(&MessageFromDatabase::Ack {
+                key: Key::from("doc"),
+                seq: SequenceNumber(1)
+            }),
+            "ack"
+        );
+    }
+
+    fn frame(frame_id: u32, part: u16, total: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+        frame.extend_from_slice(&frame_id.to_le_bytes());
+        frame.extend_from_slice(&part.to_le_bytes());
+        frame.extend_from_slice(&total.to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn reassemble_returns_single_fragment_message_immediately() {
+        let mut reassembler = FrameReassembler::default();
+
+        let result = reassembler
+            .reassemble(frame(1, 0, 1, b"hello"))
+            .unwrap();
+
+        assert_eq!(result, Some(b"hello".to_vec()));
+        assert_eq!(reassembler.pending_count(), 0);
+    }
+
+    #[test]
+    fn reassemble_waits_for_every_fragment_regardless_of_order() {
+        let mut reassembler = FrameReassembler::default();
+
+        assert_eq!(reassembler.reassemble(frame(7, 1, 2, b"world")).unwrap(), None);
+        assert_eq!(reassembler.pending_count(), 1);
+
+        let result = reassembler.reassemble(frame(7, 0, 2, b"hello ")).unwrap();
+
+        assert_eq!(result, Some(b"hello world".to_vec()));
+        assert_eq!(reassembler.pending_count(), 0);
+    }
+
+    #[test]
+    fn reassemble_keeps_distinct_frame_ids_independent() {
+        let mut reassembler = FrameReassembler::default();
+
+        assert_eq!(reassembler.reassemble(frame(1, 0, 2, b"a")).unwrap(), None);
+        assert_eq!(reassembler.reassemble(frame(2, 0, 2, b"b")).unwrap(), None);
+        assert_eq!(reassembler.pending_count(), 2);
+
+        assert_eq!(reassembler.reassemble(frame(1, 1, 2, b"c")).unwrap(), Some(b"ac".to_vec()));
+        assert_eq!(reassembler.pending_count(), 1);
+    }
+
+    #[test]
+    fn reassemble_rejects_new_message_past_the_in_flight_cap() {
+        let mut reassembler = FrameReassembler::default();
+
+        for id in 0..MAX_PARTIAL_FRAMES as u32 {
+            reassembler.reassemble(frame(id, 0, 2, b"x")).unwrap();
+        }
+        assert_eq!(reassembler.pending_count(), MAX_PARTIAL_FRAMES);
+
+        let result = reassembler.reassemble(frame(MAX_PARTIAL_FRAMES as u32, 0, 2, b"x"));
+
+        assert!(result.is_err());
+        assert_eq!(reassembler.pending_count(), MAX_PARTIAL_FRAMES);
+    }
+
+    #[test]
+    fn reassemble_rejects_short_and_malformed_frames() {
+        let mut reassembler = FrameReassembler::default();
+
+        assert!(reassembler.reassemble(vec![0u8; FRAME_HEADER_LEN - 1]).is_err());
+        // part (1) >= total (1) is not a valid fragment index.
+        assert!(reassembler.reassemble(frame(1, 1, 1, b"x")).is_err());
+    }
+
+    #[test]
+    fn idle_exactly_at_timeout_is_not_yet_timed_out() {
+        let client_timeout = Duration::from_secs(90);
+        assert!(!is_idle_timed_out(client_timeout, client_timeout));
+    }
+
+    #[test]
+    fn idle_past_timeout_is_timed_out() {
+        let client_timeout = Duration::from_secs(90);
+        assert!(is_idle_timed_out(
+            client_timeout + Duration::from_millis(1),
+            client_timeout
+        ));
+    }
+
+    #[test]
+    fn evict_stale_partials_keeps_freshly_started_reassemblies() {
+        let mut reassembler = FrameReassembler::default();
+        reassembler.reassemble(frame(1, 0, 2, b"a")).unwrap();
+
+        reassembler.evict_stale_partials();
+
+        assert_eq!(
+            reassembler.pending_count(),
+            1,
+            "a reassembly that just started is nowhere near PARTIAL_FRAME_TIMEOUT"
+        );
+    }
+}
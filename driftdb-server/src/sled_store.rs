@@ -0,0 +1,250 @@
+use anyhow::Result;
+use driftdb::{
+    types::{key_seq_pair::KeyAndSeq, SequenceNumber, SequenceValue},
+    ApplyResult, Database, DeleteInstruction, Key, PushInstruction, Store, ValueLog,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+/// An embedded [`sled`]-backed persistence layer for `driftdb-server`.
+///
+/// It mirrors the Durable Object storage scheme used by `driftdb-worker`: each
+/// retained value is stored under its [`KeyAndSeq`] encoding, so the same load
+/// and replica-callback logic applies without any Cloudflare dependency.
+pub struct SledStore {
+    tree: sled::Db,
+}
+
+impl SledStore {
+    /// Open (creating if necessary) a sled database rooted at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            tree: sled::open(path)?,
+        })
+    }
+
+    /// Build a [`Database`] whose state is restored from, and whose mutations
+    /// are persisted to, this sled tree.
+    pub fn into_database(self) -> Result<Database> {
+        let store = self.load_store()?;
+        let mut db = Database::new_from_store(store);
+
+        let tree = self.tree.clone();
+        db.set_replica_callback(move |apply_result: &ApplyResult| {
+            if let Err(e) = persist(&tree, apply_result) {
+                tracing::error!(?e, "Failed to persist apply result to sled.");
+            }
+            // `persist` above is synchronous, so on success this is exactly
+            // the moment a `Durability::Hard` push becomes durable. Today
+            // that moment is silent: this callback has no handle back to
+            // `Database`'s broadcast so it can't emit the
+            // `MessageFromDatabase::Ack { key, seq }` a waiting client needs
+            // (see the comment on the Ack path in `server.rs::handle_socket`
+            // for why no correlation id is required to do this soundly).
+            // Until `Database` exposes a way to broadcast out-of-band from a
+            // replica callback, `Durability::Hard` against this backend is
+            // indistinguishable from `Soft`.
+        });
+
+        Ok(db)
+    }
+
+    /// Rebuild a [`Store`] by scanning the whole tree, recomputing `max_seq`.
+    fn load_store(&self) -> Result<Store> {
+        let mut subjects = BTreeMap::<Key, ValueLog>::new();
+        let mut max_seq = 0;
+
+        for entry in self.tree.iter() {
+            let (key, value) = entry?;
+            let key = std::str::from_utf8(&key)?;
+            let key_and_seq = KeyAndSeq::from_str(key)?;
+            max_seq = max_seq.max(key_and_seq.seq.0);
+            let value: Value = serde_json::from_slice(&value)?;
+
+            subjects
+                .entry(key_and_seq.key)
+                .or_insert_with(ValueLog::default)
+                .values
+                .push_back(SequenceValue {
+                    value,
+                    seq: key_and_seq.seq,
+                });
+        }
+
+        Ok(Store::new(subjects, SequenceNumber(max_seq)))
+    }
+}
+
+/// Translate a single [`ApplyResult`] into the matching sled mutations.
+fn persist(tree: &sled::Db, apply_result: &ApplyResult) -> Result<()> {
+    if let Some(delete_instruction) = &apply_result.delete_instruction {
+        let prefix = KeyAndSeq::prefix_str(&apply_result.key);
+
+        match delete_instruction {
+            DeleteInstruction::Delete => {
+                for entry in tree.scan_prefix(prefix.as_bytes()) {
+                    let (key, _) = entry?;
+                    tree.remove(key)?;
+                }
+            }
+            DeleteInstruction::DeleteUpTo(seq) => {
+                let end = KeyAndSeq::new(apply_result.key.clone(), seq.next()).to_string();
+                for entry in tree.range(prefix.as_bytes()..end.as_bytes()) {
+                    let (key, _) = entry?;
+                    tree.remove(key)?;
+                }
+            }
+        }
+    }
+
+    if let Some(push_instruction) = &apply_result.push_instruction {
+        let sequence_value = match push_instruction {
+            PushInstruction::Push(sequence_value) => sequence_value,
+            PushInstruction::PushStart(sequence_value) => sequence_value,
+        };
+
+        let storage_key =
+            KeyAndSeq::new(apply_result.key.clone(), sequence_value.seq).to_string();
+        let storage_value = serde_json::to_vec(&sequence_value.value)?;
+
+        tree.insert(storage_key.as_bytes(), storage_value)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use driftdb::types::Durability;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// A throwaway sled tree under the system temp dir, removed on drop so
+    /// repeated test runs don't pile up state from previous ones.
+    struct TempTree {
+        path: std::path::PathBuf,
+        tree: sled::Db,
+    }
+
+    impl TempTree {
+        fn open() -> Self {
+            static COUNTER: AtomicU32 = AtomicU32::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "driftdb-sled-store-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = std::fs::remove_dir_all(&path);
+            let tree = sled::open(&path).expect("failed to open temp sled tree");
+            Self { path, tree }
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    /// Values persisted via `persist` must come back out of `load_store`
+    /// under the same key, in sequence order.
+    #[test]
+    fn persisted_values_round_trip_through_load_store() {
+        let temp = TempTree::open();
+
+        let key = Key::from("doc");
+        let first = ApplyResult {
+            key: key.clone(),
+            delete_instruction: None,
+            push_instruction: Some(PushInstruction::Push(SequenceValue {
+                value: json!("hello"),
+                seq: SequenceNumber(1),
+            })),
+            broadcast: None,
+            error: None,
+            subject_size: 0,
+            durability: Durability::Soft,
+        };
+        let second = ApplyResult {
+            push_instruction: Some(PushInstruction::Push(SequenceValue {
+                value: json!("world"),
+                seq: SequenceNumber(2),
+            })),
+            ..first.clone()
+        };
+
+        persist(&temp.tree, &first).unwrap();
+        persist(&temp.tree, &second).unwrap();
+
+        let store = SledStore {
+            tree: temp.tree.clone(),
+        }
+        .load_store()
+        .unwrap();
+
+        let dumped = store.dump(SequenceNumber(0));
+        assert_eq!(dumped.len(), 1);
+        let (dumped_key, values) = &dumped[0];
+        assert_eq!(dumped_key, &key);
+        assert_eq!(
+            values.iter().map(|v| v.value.clone()).collect::<Vec<_>>(),
+            vec![json!("hello"), json!("world")]
+        );
+    }
+
+    /// A `DeleteUpTo` instruction persisted after some pushes must remove
+    /// exactly the entries at or below that sequence number from the tree,
+    /// the same as it does from the in-memory `Store`.
+    #[test]
+    fn delete_up_to_removes_only_older_entries() {
+        let temp = TempTree::open();
+        let key = Key::from("doc");
+
+        for seq in 1..=3u64 {
+            persist(
+                &temp.tree,
+                &ApplyResult {
+                    key: key.clone(),
+                    delete_instruction: None,
+                    push_instruction: Some(PushInstruction::Push(SequenceValue {
+                        value: json!(seq),
+                        seq: SequenceNumber(seq),
+                    })),
+                    broadcast: None,
+                    error: None,
+                    subject_size: 0,
+                    durability: Durability::Soft,
+                },
+            )
+            .unwrap();
+        }
+
+        persist(
+            &temp.tree,
+            &ApplyResult {
+                key: key.clone(),
+                delete_instruction: Some(DeleteInstruction::DeleteUpTo(SequenceNumber(2))),
+                push_instruction: None,
+                broadcast: None,
+                error: None,
+                subject_size: 0,
+                durability: Durability::Soft,
+            },
+        )
+        .unwrap();
+
+        let store = SledStore {
+            tree: temp.tree.clone(),
+        }
+        .load_store()
+        .unwrap();
+
+        let dumped = store.dump(SequenceNumber(0));
+        let (_, values) = &dumped[0];
+        assert_eq!(
+            values.iter().map(|v| v.seq).collect::<Vec<_>>(),
+            vec![SequenceNumber(3)]
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use driftdb::{
-    types::{key_seq_pair::KeyAndSeq, SequenceNumber, SequenceValue},
+    types::{key_seq_pair::KeyAndSeq, Durability, MessageFromDatabase, SequenceNumber, SequenceValue},
     ApplyResult, Database, DeleteInstruction, Key, PushInstruction, Store, ValueLog,
 };
 use gloo_utils::format::JsValueSerdeExt;
 use serde_json::Value;
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
 use worker::{console_log, wasm_bindgen_futures};
 use worker::{ListOptions, Result, State};
 
@@ -27,13 +27,22 @@ compile_error!(
 pub struct PersistedDb {
     state: WrappedState,
     db: Option<Database>,
+
+    /// Invoked with a [`MessageFromDatabase::Ack`] once a `Hard` push's
+    /// storage `put` resolves. Supplied by the caller of [`PersistedDb::new`];
+    /// `PersistedDb` itself sends nothing to any client, so delivering the
+    /// Ack is up to that handler.
+    on_ack: Arc<dyn Fn(MessageFromDatabase) + Send + Sync>,
 }
 
 impl PersistedDb {
-    pub fn new(state: State) -> Self {
+    /// `on_ack` is called with the [`MessageFromDatabase::Ack`] of each `Hard`
+    /// push once its storage `put` resolves; see the `on_ack` field.
+    pub fn new(state: State, on_ack: impl Fn(MessageFromDatabase) + Send + Sync + 'static) -> Self {
         Self {
             state: WrappedState::new(state),
             db: None,
+            on_ack: Arc::new(on_ack),
         }
     }
 
@@ -62,9 +71,11 @@ impl PersistedDb {
 
         {
             let state = self.state.clone();
+            let on_ack = self.on_ack.clone();
             db.set_replica_callback(move |apply_result: &ApplyResult| {
                 let mut storage = state.0.storage();
                 let apply_result = apply_result.clone();
+                let on_ack = on_ack.clone();
 
                 wasm_bindgen_futures::spawn_local(async move {
                     if let Some(delete_instruction) = &apply_result.delete_instruction {
@@ -125,6 +136,16 @@ impl PersistedDb {
                             .put(&storage_key, &storage_value)
                             .await
                             .expect("Error putting value in storage.");
+
+                        // Only a `Hard` push promised an Ack; a `Soft` one was
+                        // already broadcast before this callback ever ran, so
+                        // acking it here would be a second, unrequested signal.
+                        if apply_result.durability == Durability::Hard {
+                            on_ack(MessageFromDatabase::Ack {
+                                key: apply_result.key.clone(),
+                                seq: sequence_value.seq,
+                            });
+                        }
                     }
                 });
             });
@@ -136,7 +157,7 @@ impl PersistedDb {
 
     async fn load_store(&self, state: &State) -> Result<Store> {
         let storage = state.storage();
-        let mut subjects = HashMap::<Key, ValueLog>::new();
+        let mut subjects = BTreeMap::<Key, ValueLog>::new();
         let data = storage.list().await?;
 
         let mut max_seq = 0;
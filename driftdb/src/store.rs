@@ -1,15 +1,21 @@
-use crate::types::{Action, Key, SequenceNumber, SequenceValue};
+use crate::types::{Action, Durability, Key, OperationSeq, SequenceNumber, SequenceValue};
 use serde_json::Value;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Default)]
-struct ValueLog {
-    values: VecDeque<SequenceValue>,
+pub struct ValueLog {
+    pub values: VecDeque<SequenceValue>,
+
+    /// The authoritative text document edited by `Action::Edit`. Folded
+    /// forward as each edit commits so rebased ops can be validated against it.
+    document: String,
 }
 
 #[derive(Default)]
 pub struct Store {
-    subjects: HashMap<Key, ValueLog>,
+    // A `BTreeMap` keeps subjects iterable in key order so `scan` can paginate
+    // deterministically and resume from a cursor.
+    subjects: BTreeMap<Key, ValueLog>,
     sequence_number: SequenceNumber,
 }
 
@@ -31,7 +37,12 @@ pub enum PushInstruction {
     PushStart(SequenceValue),
 }
 
+#[derive(Debug, Clone)]
 pub struct ApplyResult {
+    /// The subject the instructions below apply to. Carried so replica
+    /// callbacks can address the right key in their backing store.
+    pub key: Key,
+
     /// Optional instruction to remove some or all existing values.
     pub delete_instruction: Option<DeleteInstruction>,
 
@@ -41,8 +52,20 @@ pub struct ApplyResult {
     /// Optional value to broadcast to clients.
     pub broadcast: Option<SequenceValue>,
 
+    /// Set when the action was rejected outright instead of applied, e.g. an
+    /// `Edit` whose ops could not be rebased onto the current document.
+    /// Unlike `broadcast`, this is not for every subscriber: the caller
+    /// should send it back to the client that submitted the action only, as
+    /// a [`crate::types::MessageFromDatabase::Error`].
+    pub error: Option<String>,
+
     /// The number of retained records for the given subject after applying the action.
     pub subject_size: usize,
+
+    /// The durability requested for this action. Carried through so a
+    /// replica callback can gate a [`crate::types::MessageFromDatabase::Ack`]
+    /// on `Hard` without having to thread its own side channel for it.
+    pub durability: Durability,
 }
 
 impl ApplyResult {
@@ -52,6 +75,15 @@ impl ApplyResult {
 }
 
 impl Store {
+    /// Rebuild a store from a set of subjects and the highest sequence number
+    /// seen, used by persistence backends to restore state at boot.
+    pub fn new(subjects: BTreeMap<Key, ValueLog>, sequence_number: SequenceNumber) -> Self {
+        Self {
+            subjects,
+            sequence_number,
+        }
+    }
+
     fn next_seq(&mut self) -> SequenceNumber {
         self.sequence_number.0 += 1;
         self.sequence_number
@@ -74,17 +106,110 @@ impl Store {
             .collect()
     }
 
-    pub fn apply(&mut self, key: &Key, value: Value, action: &Action) -> ApplyResult {
+    /// Rebase a client's edit `ops` (authored at `base_seq`) onto the current
+    /// head of `key` by transforming them through every edit committed since.
+    ///
+    /// Returns the rebased ops and the resulting document, or `None` if the
+    /// ops cannot be reconciled with the stream's current state.
+    fn rebase_edit(
+        &self,
+        key: &Key,
+        base_seq: SequenceNumber,
+        ops: &OperationSeq,
+    ) -> Option<(OperationSeq, String)> {
+        let value_log = self.subjects.get(key);
+        let document = value_log.map(|v| v.document.as_str()).unwrap_or("");
+
+        let mut rebased = ops.clone();
+        if let Some(value_log) = value_log {
+            for committed in value_log.values.iter().filter(|v| v.seq > base_seq) {
+                let committed_ops: OperationSeq =
+                    match serde_json::from_value(committed.value.clone()) {
+                        Ok(ops) => ops,
+                        // Non-edit records in the log are not part of the
+                        // concurrent-edit history; skip them.
+                        Err(_) => continue,
+                    };
+
+                rebased = match rebased.transform(&committed_ops) {
+                    Ok((rebased, _)) => rebased,
+                    Err(_) => return None,
+                };
+            }
+        }
+
+        if rebased.base_len() != document.chars().count() as u64 {
+            return None;
+        }
+
+        let new_document = rebased.apply(document).ok()?;
+        Some((rebased, new_document))
+    }
+
+    /// Return a page of keys sharing `prefix`, in sorted order, resuming from
+    /// `start_after` (inclusive) when given. At most `limit` keys are
+    /// returned; the second element is the key to pass back as `start_after`
+    /// to continue, or `None` when no further matching keys remain.
+    ///
+    /// Uses `BTreeMap::range` to seek directly to `start_after`/`prefix`
+    /// instead of walking every subject, so a page costs `O(log n + limit)`
+    /// rather than a full scan of the store.
+    pub fn scan(
+        &self,
+        prefix: &str,
+        start_after: Option<&Key>,
+        limit: usize,
+    ) -> (Vec<(Key, Vec<SequenceValue>)>, Option<Key>) {
+        use std::ops::Bound;
+
+        let lower = match start_after {
+            Some(key) => Bound::Included(key.clone()),
+            None => Bound::Included(Key::from(prefix)),
+        };
+
+        let mut matching = self
+            .subjects
+            .range((lower, Bound::Unbounded))
+            .take_while(|(key, _)| key.as_str().starts_with(prefix));
+
+        let mut items: Vec<(Key, Vec<SequenceValue>)> = Vec::new();
+        let mut next = None;
+
+        for (key, value_log) in &mut matching {
+            if items.len() == limit {
+                // The cursor is the first key of the *next* page, so a page
+                // of `limit == 0` still reports it rather than looking
+                // identical to a truly exhausted prefix.
+                next = Some(key.clone());
+                break;
+            }
+
+            items.push((key.clone(), value_log.values.iter().cloned().collect()));
+        }
+
+        (items, next)
+    }
+
+    pub fn apply(
+        &mut self,
+        key: &Key,
+        value: Value,
+        action: &Action,
+        durability: Durability,
+    ) -> ApplyResult {
         let mut result = match action {
             Action::Append => {
                 let seq = self.next_seq();
                 let value = SequenceValue { value, seq };
 
                 ApplyResult {
+                    key: key.clone(),
                     delete_instruction: None,
                     push_instruction: Some(PushInstruction::Push(value.clone())),
                     broadcast: Some(value),
+                    error: None,
                     subject_size: 0,
+                    durability,
                 }
             }
             Action::Replace => {
@@ -92,30 +217,81 @@ impl Store {
                 let value = SequenceValue { value, seq };
 
                 ApplyResult {
+                    key: key.clone(),
                     delete_instruction: Some(DeleteInstruction::Delete),
                     push_instruction: Some(PushInstruction::Push(value.clone())),
                     broadcast: Some(value),
+                    error: None,
                     subject_size: 0,
+                    durability,
                 }
             }
             Action::Compact { seq } => ApplyResult {
+                key: key.clone(),
                 delete_instruction: Some(DeleteInstruction::DeleteUpTo(*seq)),
                 push_instruction: Some(PushInstruction::PushStart(SequenceValue {
                     value,
                     seq: *seq,
                 })),
                 broadcast: None,
+                error: None,
                 subject_size: 0,
+                durability,
             },
             Action::Relay => {
                 let seq = self.next_seq();
                 ApplyResult {
+                    key: key.clone(),
                     delete_instruction: None,
                     push_instruction: None,
                     broadcast: Some(SequenceValue { value, seq }),
+                    error: None,
                     subject_size: 0,
+                    durability,
                 }
             }
+            Action::Edit { base_seq, ops } => match self.rebase_edit(key, *base_seq, ops) {
+                Some((rebased, new_document)) => {
+                    let seq = self.next_seq();
+                    self.subjects.entry(key.clone()).or_default().document = new_document;
+
+                    // Broadcast the *transformed* op so other clients can apply
+                    // it against their own local document.
+                    let value = SequenceValue {
+                        value: serde_json::to_value(&rebased)
+                            .expect("OperationSeq always serializes to JSON."),
+                        seq,
+                    };
+
+                    ApplyResult {
+                        key: key.clone(),
+                        delete_instruction: None,
+                        push_instruction: Some(PushInstruction::Push(value.clone())),
+                        broadcast: Some(value),
+                        error: None,
+                        subject_size: 0,
+                        durability,
+                    }
+                }
+                // The edit could not be rebased onto the current document (a
+                // stale `base_seq` or a conflicting concurrent edit). Unlike a
+                // stale `Compact`, the submitting client has no other way to
+                // learn its edit never landed and would silently drift from
+                // the document, so surface it as an error instead of a no-op.
+                None => ApplyResult {
+                    key: key.clone(),
+                    delete_instruction: None,
+                    push_instruction: None,
+                    broadcast: None,
+                    error: Some(format!(
+                        "Edit at base_seq {} could not be rebased onto the current document; \
+                         resync with a Dump and retry.",
+                        base_seq.0
+                    )),
+                    subject_size: 0,
+                    durability,
+                },
+            },
         };
 
         match &result.delete_instruction {
@@ -147,3 +323,126 @@ impl Store {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(base_seq: u64, ops: OperationSeq) -> Action {
+        Action::Edit {
+            base_seq: SequenceNumber(base_seq),
+            ops,
+        }
+    }
+
+    /// A second client's edit, authored against the same `base_seq` as one
+    /// already committed, must be rebased through it rather than clobbering
+    /// it the way `Replace` would.
+    #[test]
+    fn edit_rebases_through_committed_history() {
+        let mut store = Store::default();
+        let key = Key::from("doc");
+
+        let mut first = OperationSeq::default();
+        first.insert("abc");
+        let result = store.apply(&key, Value::Null, &edit(0, first), Durability::Soft);
+        assert!(result.error.is_none());
+
+        let mut second = OperationSeq::default();
+        second.insert("X");
+        let result = store.apply(&key, Value::Null, &edit(0, second), Durability::Soft);
+
+        assert!(result.error.is_none(), "rebase should have succeeded");
+        match result.push_instruction {
+            Some(PushInstruction::Push(value)) => {
+                let rebased: OperationSeq = serde_json::from_value(value.value).unwrap();
+                assert_eq!(rebased.apply("abc").unwrap(), "Xabc");
+            }
+            other => panic!("expected a Push instruction, got {:?}", other),
+        }
+    }
+
+    /// An edit that cannot be reconciled with the document's current state
+    /// (e.g. a stale `base_seq` whose ops no longer fit) must surface an
+    /// error instead of silently being dropped, since the client has no
+    /// other way to learn it never landed.
+    #[test]
+    fn edit_rejected_when_ops_no_longer_fit_document() {
+        let mut store = Store::default();
+        let key = Key::from("doc");
+
+        let mut first = OperationSeq::default();
+        first.insert("abc");
+        let result = store.apply(&key, Value::Null, &edit(0, first), Durability::Soft);
+        assert!(result.error.is_none());
+
+        // Authored against the post-commit head (so no transform is needed)
+        // but with ops that assume a five-character document instead of the
+        // real three-character one.
+        let mut stale = OperationSeq::default();
+        stale.delete(5);
+        let result = store.apply(&key, Value::Null, &edit(1, stale), Durability::Soft);
+
+        assert!(result.error.is_some());
+        assert!(!result.mutates());
+    }
+
+    fn append(store: &mut Store, key: &Key) {
+        store.apply(key, Value::Null, &Action::Append, Durability::Soft);
+    }
+
+    /// A page stops at `limit` and returns the first key of the *next* page
+    /// as its cursor, rather than the last key it returned.
+    #[test]
+    fn scan_pages_and_cursor_point_at_the_next_unread_key() {
+        let mut store = Store::default();
+        for k in ["a/1", "a/2", "a/3"] {
+            append(&mut store, &Key::from(k));
+        }
+
+        let (items, next) = store.scan("a/", None, 2);
+
+        assert_eq!(
+            items.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            vec!["a/1", "a/2"]
+        );
+        assert_eq!(next, Some(Key::from("a/3")));
+    }
+
+    /// Passing the previous page's cursor back as `start_after` resumes
+    /// exactly where the first page left off, since the cursor is inclusive.
+    #[test]
+    fn scan_resumes_from_start_after_cursor() {
+        let mut store = Store::default();
+        for k in ["a/1", "a/2", "a/3"] {
+            append(&mut store, &Key::from(k));
+        }
+
+        let (_, next) = store.scan("a/", None, 2);
+        let (items, next) = store.scan("a/", next.as_ref(), 2);
+
+        assert_eq!(
+            items.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            vec!["a/3"]
+        );
+        assert_eq!(next, None, "prefix is exhausted after the last key");
+    }
+
+    /// Keys outside the prefix, including ones that sort between matching
+    /// keys, must never appear in a page or leak into its cursor.
+    #[test]
+    fn scan_only_returns_keys_matching_the_prefix() {
+        let mut store = Store::default();
+        for k in ["a/1", "b/1", "a/2"] {
+            append(&mut store, &Key::from(k));
+        }
+
+        let (items, next) = store.scan("a/", None, 10);
+
+        assert_eq!(
+            items.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            vec!["a/1", "a/2"]
+        );
+        assert_eq!(next, None);
+    }
+}
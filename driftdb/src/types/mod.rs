@@ -1,7 +1,13 @@
+pub mod operation_seq;
+
+pub use operation_seq::OperationSeq;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Default, Deserialize, Hash)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, Serialize, Default, Deserialize, Hash, PartialOrd, Ord,
+)]
 pub struct Key(String);
 
 impl From<&str> for Key {
@@ -10,6 +16,13 @@ impl From<&str> for Key {
     }
 }
 
+impl Key {
+    /// The key's underlying string, for prefix comparisons during `Scan`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default, PartialOrd, Ord)]
 pub struct SequenceNumber(pub u64);
 
@@ -29,6 +42,37 @@ pub enum Action {
     /// If the stream has already been rolled up to an equal or greater
     /// sequence number, this is ignored.
     Compact { seq: SequenceNumber },
+
+    /// Apply a text edit to the key's authoritative document concurrently.
+    ///
+    /// The ops are authored against the document as it stood at `base_seq`; the
+    /// store rebases them through every edit committed since before applying,
+    /// so concurrent editors converge instead of clobbering each other the way
+    /// `Replace` does. If the rebased ops no longer fit the current document
+    /// the edit is not applied and an error is returned to the submitter.
+    Edit {
+        base_seq: SequenceNumber,
+        ops: OperationSeq,
+    },
+}
+
+/// Durability level requested for a [`MessageToDatabase::Push`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Durability {
+    /// Broadcast the value immediately; do not wait for it to be persisted.
+    #[default]
+    Soft,
+
+    /// Broadcast the value and emit a [`MessageFromDatabase::Ack`] only once the
+    /// replica has durably stored it.
+    ///
+    /// driftdb-worker's replica callback emits the Ack once the storage `put`
+    /// resolves, through the `on_ack` handler passed to `PersistedDb::new`.
+    /// driftdb-server's replica (sled-backed or in-memory) emits no Ack, so
+    /// against that backend this behaves like `Soft` — see
+    /// `sled_store.rs::into_database`.
+    Hard,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -43,11 +87,27 @@ pub enum MessageToDatabase {
 
         /// Describes the action that this should have on the state.
         action: Action,
+
+        /// How durable the write must be before it is acknowledged.
+        #[serde(default)]
+        durability: Durability,
     },
     Dump {
         /// Sequence number to start from.
         seq: SequenceNumber,
     },
+    Scan {
+        /// Only keys starting with this prefix are returned.
+        prefix: String,
+
+        /// Resume from this key (inclusive); `None` starts from the first
+        /// matching key.
+        #[serde(default)]
+        start_after: Option<Key>,
+
+        /// Maximum number of keys to return in this page.
+        limit: usize,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -63,9 +123,28 @@ pub enum MessageFromDatabase {
         key: Key,
         value: SequenceValue,
     },
+    /// Confirms that a `Hard`-durability push for `key` has been persisted up to
+    /// `seq`. Emitted only after the replica's storage write resolves.
+    ///
+    /// Only driftdb-worker emits this today; see [`Durability::Hard`].
+    Ack {
+        key: Key,
+        seq: SequenceNumber,
+    },
     Init {
         data: Vec<(Key, Vec<SequenceValue>)>,
     },
+    /// A page of keys sharing a prefix, in sorted order, answering a `Scan`.
+    ScanResult {
+        items: Vec<(Key, Vec<SequenceValue>)>,
+
+        /// Cursor for the next page, or `None` when the prefix is exhausted.
+        /// Pass it back as `Scan::start_after` to continue; since it is
+        /// inclusive this is populated even when `items` is empty (e.g. a
+        /// page requested with `limit: 0`), so an empty page can't be
+        /// mistaken for an exhausted prefix.
+        next: Option<Key>,
+    },
     Error {
         message: String,
     },
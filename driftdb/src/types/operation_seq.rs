@@ -0,0 +1,386 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single component of an [`OperationSeq`].
+///
+/// Operations are expressed relative to a document of a known length: the sum
+/// of every `Retain` and `Delete` length must equal the document the sequence
+/// was authored against, and the resulting document is the sum of the
+/// `Retain`ed and `Insert`ed characters.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Operation {
+    /// Keep `n` characters from the source document unchanged.
+    Retain(u64),
+
+    /// Insert the given string at the current position.
+    Insert(String),
+
+    /// Drop `n` characters from the source document.
+    Delete(u64),
+}
+
+/// An ordered list of [`Operation`]s describing an edit to a text document,
+/// together with the document length it expects (`base_len`) and the length it
+/// produces (`target_len`).
+///
+/// The invariant `base_len`/`target_len` let two concurrently authored
+/// sequences be [`transform`](OperationSeq::transform)ed against one another so
+/// that applying them in either order converges to the same document, which is
+/// what makes `Action::Edit` safe for concurrent collaborative editing.
+///
+/// The fields arrive straight from untrusted client JSON, so deserialization
+/// runs [`check`](OperationSeq::check): the declared `base_len`/`target_len`
+/// must agree with the ops, otherwise a malformed sequence (e.g. a `Retain`
+/// longer than the document) could panic [`apply`](OperationSeq::apply).
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Default)]
+#[serde(try_from = "OperationSeqRepr")]
+pub struct OperationSeq {
+    ops: Vec<Operation>,
+    base_len: u64,
+    target_len: u64,
+}
+
+/// Unvalidated wire form of an [`OperationSeq`]; deserialized and then checked
+/// for internal consistency before becoming an `OperationSeq`.
+#[derive(Deserialize)]
+struct OperationSeqRepr {
+    ops: Vec<Operation>,
+    base_len: u64,
+    target_len: u64,
+}
+
+impl TryFrom<OperationSeqRepr> for OperationSeq {
+    type Error = OtError;
+
+    fn try_from(repr: OperationSeqRepr) -> Result<Self, Self::Error> {
+        let seq = OperationSeq {
+            ops: repr.ops,
+            base_len: repr.base_len,
+            target_len: repr.target_len,
+        };
+        seq.check()?;
+        Ok(seq)
+    }
+}
+
+/// Error returned when an [`OperationSeq`] does not line up with the document or
+/// sequence it is being combined with.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OtError {
+    /// The sequence's `base_len` does not match the document it was applied to.
+    BaseLenMismatch { expected: u64, actual: u64 },
+
+    /// Two sequences handed to [`transform`](OperationSeq::transform) were not
+    /// authored against a document of the same length.
+    IncompatibleLengths { a: u64, b: u64 },
+
+    /// The sequence's ops do not add up to its declared `base_len`/`target_len`,
+    /// so it could not be trusted to apply without overrunning the document.
+    Malformed {
+        declared_base: u64,
+        declared_target: u64,
+        actual_base: u64,
+        actual_target: u64,
+    },
+}
+
+impl fmt::Display for OtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OtError::BaseLenMismatch { expected, actual } => write!(
+                f,
+                "operation expected a document of length {} but it was {}",
+                expected, actual
+            ),
+            OtError::IncompatibleLengths { a, b } => write!(
+                f,
+                "cannot transform operations authored against documents of length {} and {}",
+                a, b
+            ),
+            OtError::Malformed {
+                declared_base,
+                declared_target,
+                actual_base,
+                actual_target,
+            } => write!(
+                f,
+                "operation declares base_len {}/target_len {} but its ops sum to {}/{}",
+                declared_base, declared_target, actual_base, actual_target
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OtError {}
+
+impl OperationSeq {
+    /// The document length this sequence expects to be applied to.
+    pub fn base_len(&self) -> u64 {
+        self.base_len
+    }
+
+    /// The document length this sequence produces once applied.
+    pub fn target_len(&self) -> u64 {
+        self.target_len
+    }
+
+    /// Verify the ops are internally consistent with the declared lengths: the
+    /// sum of every `Retain` and `Delete` must equal `base_len`, and the sum of
+    /// every `Retain` and `Insert` must equal `target_len`. Enforced on
+    /// deserialize so a malformed client sequence can never reach
+    /// [`apply`](Self::apply) and index past the document.
+    pub fn check(&self) -> Result<(), OtError> {
+        let mut base = 0u64;
+        let mut target = 0u64;
+        for op in &self.ops {
+            match op {
+                Operation::Retain(n) => {
+                    base += n;
+                    target += n;
+                }
+                Operation::Insert(s) => target += s.chars().count() as u64,
+                Operation::Delete(n) => base += n,
+            }
+        }
+
+        if base != self.base_len || target != self.target_len {
+            return Err(OtError::Malformed {
+                declared_base: self.base_len,
+                declared_target: self.target_len,
+                actual_base: base,
+                actual_target: target,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Keep `n` characters unchanged, coalescing with a trailing `Retain`.
+    pub fn retain(&mut self, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.base_len += n;
+        self.target_len += n;
+        if let Some(Operation::Retain(last)) = self.ops.last_mut() {
+            *last += n;
+        } else {
+            self.ops.push(Operation::Retain(n));
+        }
+    }
+
+    /// Insert `s` at the current position, coalescing with a trailing `Insert`.
+    pub fn insert(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        self.target_len += s.chars().count() as u64;
+        if let Some(Operation::Insert(last)) = self.ops.last_mut() {
+            last.push_str(s);
+        } else {
+            self.ops.push(Operation::Insert(s.to_string()));
+        }
+    }
+
+    /// Drop `n` characters, coalescing with a trailing `Delete`.
+    pub fn delete(&mut self, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.base_len += n;
+        if let Some(Operation::Delete(last)) = self.ops.last_mut() {
+            *last += n;
+        } else {
+            self.ops.push(Operation::Delete(n));
+        }
+    }
+
+    /// Apply this sequence to `doc`, producing the edited document.
+    ///
+    /// Fails if `doc`'s length does not match [`base_len`](Self::base_len).
+    pub fn apply(&self, doc: &str) -> Result<String, OtError> {
+        let chars: Vec<char> = doc.chars().collect();
+        if chars.len() as u64 != self.base_len {
+            return Err(OtError::BaseLenMismatch {
+                expected: self.base_len,
+                actual: chars.len() as u64,
+            });
+        }
+
+        let mut out = String::new();
+        let mut idx = 0usize;
+        for op in &self.ops {
+            match op {
+                Operation::Retain(n) => {
+                    // Checked so a hand-built or mistransformed sequence returns
+                    // an error rather than panicking on an out-of-range slice.
+                    let end = idx
+                        .checked_add(*n as usize)
+                        .filter(|&end| end <= chars.len())
+                        .ok_or(OtError::BaseLenMismatch {
+                            expected: self.base_len,
+                            actual: chars.len() as u64,
+                        })?;
+                    out.extend(&chars[idx..end]);
+                    idx = end;
+                }
+                Operation::Insert(s) => out.push_str(s),
+                Operation::Delete(n) => {
+                    idx = idx
+                        .checked_add(*n as usize)
+                        .filter(|&end| end <= chars.len())
+                        .ok_or(OtError::BaseLenMismatch {
+                            expected: self.base_len,
+                            actual: chars.len() as u64,
+                        })?;
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Transform two concurrently authored sequences `self` (`a`) and `other`
+    /// (`b`), both based on the same document, into the pair `(a', b')` such
+    /// that `apply(apply(doc, a), b') == apply(apply(doc, b), a')`.
+    ///
+    /// Where both sides touch the same characters, retains advance both,
+    /// inserts are ordered with `self`'s insertions first, and a delete on one
+    /// side removes the characters the other side would have retained.
+    pub fn transform(
+        &self,
+        other: &OperationSeq,
+    ) -> Result<(OperationSeq, OperationSeq), OtError> {
+        if self.base_len != other.base_len {
+            return Err(OtError::IncompatibleLengths {
+                a: self.base_len,
+                b: other.base_len,
+            });
+        }
+
+        let mut a_prime = OperationSeq::default();
+        let mut b_prime = OperationSeq::default();
+
+        let mut ops_a = self.ops.iter();
+        let mut ops_b = other.ops.iter();
+        let mut maybe_a = ops_a.next().cloned();
+        let mut maybe_b = ops_b.next().cloned();
+
+        loop {
+            match (maybe_a.take(), maybe_b.take()) {
+                (None, None) => break,
+
+                // Insertions are applied eagerly; `self`'s insert wins the tie.
+                (Some(Operation::Insert(s)), b) => {
+                    a_prime.insert(&s);
+                    b_prime.retain(s.chars().count() as u64);
+                    maybe_a = ops_a.next().cloned();
+                    maybe_b = b;
+                }
+                (a, Some(Operation::Insert(s))) => {
+                    a_prime.retain(s.chars().count() as u64);
+                    b_prime.insert(&s);
+                    maybe_a = a;
+                    maybe_b = ops_b.next().cloned();
+                }
+
+                (Some(Operation::Retain(a)), Some(Operation::Retain(b))) => {
+                    let min = a.min(b);
+                    a_prime.retain(min);
+                    b_prime.retain(min);
+                    maybe_a = remainder(Operation::Retain(a), min, &mut ops_a);
+                    maybe_b = remainder(Operation::Retain(b), min, &mut ops_b);
+                }
+                (Some(Operation::Delete(a)), Some(Operation::Delete(b))) => {
+                    // Both sides deleted the same span; neither needs to repeat it.
+                    let min = a.min(b);
+                    maybe_a = remainder(Operation::Delete(a), min, &mut ops_a);
+                    maybe_b = remainder(Operation::Delete(b), min, &mut ops_b);
+                }
+                (Some(Operation::Delete(a)), Some(Operation::Retain(b))) => {
+                    let min = a.min(b);
+                    a_prime.delete(min);
+                    maybe_a = remainder(Operation::Delete(a), min, &mut ops_a);
+                    maybe_b = remainder(Operation::Retain(b), min, &mut ops_b);
+                }
+                (Some(Operation::Retain(a)), Some(Operation::Delete(b))) => {
+                    let min = a.min(b);
+                    b_prime.delete(min);
+                    maybe_a = remainder(Operation::Retain(a), min, &mut ops_a);
+                    maybe_b = remainder(Operation::Delete(b), min, &mut ops_b);
+                }
+
+                (None, _) | (_, None) => {
+                    return Err(OtError::IncompatibleLengths {
+                        a: self.base_len,
+                        b: other.base_len,
+                    })
+                }
+            }
+        }
+
+        Ok((a_prime, b_prime))
+    }
+}
+
+/// Consume `min` units of a `Retain`/`Delete` op, yielding the leftover if the
+/// op was longer than `min`, otherwise advancing `rest` to the next op.
+fn remainder<'a, I: Iterator<Item = &'a Operation>>(
+    op: Operation,
+    min: u64,
+    rest: &mut I,
+) -> Option<Operation> {
+    match op {
+        Operation::Retain(n) if n > min => Some(Operation::Retain(n - min)),
+        Operation::Delete(n) if n > min => Some(Operation::Delete(n - min)),
+        _ => rest.next().cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A concurrent insert (`b`) and delete (`a`) against the same document
+    /// must converge to the same result regardless of which side applies
+    /// first, per the invariant documented on `transform`.
+    #[test]
+    fn transform_converges_for_concurrent_insert_and_delete() {
+        let doc = "abc";
+
+        let mut a = OperationSeq::default();
+        a.delete(1);
+        a.retain(2);
+
+        let mut b = OperationSeq::default();
+        b.retain(1);
+        b.insert("Y");
+        b.retain(2);
+
+        let (a_prime, b_prime) = a.transform(&b).unwrap();
+
+        let via_a_then_b = b_prime.apply(&a.apply(doc).unwrap()).unwrap();
+        let via_b_then_a = a_prime.apply(&b.apply(doc).unwrap()).unwrap();
+
+        assert_eq!(via_a_then_b, via_b_then_a);
+        assert_eq!(via_a_then_b, "Ybc");
+    }
+
+    /// A sequence whose ops don't sum to its declared `base_len`/`target_len`
+    /// must be rejected by `check` on deserialize, not reach `apply` where it
+    /// could index past the document.
+    #[test]
+    fn malformed_base_len_rejected_on_deserialize() {
+        let mut seq = OperationSeq::default();
+        seq.retain(2);
+        seq.insert("hi");
+
+        let json = serde_json::to_string(&seq).unwrap();
+        let corrupted = json.replace(r#""base_len":2"#, r#""base_len":99"#);
+        assert_ne!(json, corrupted, "expected to find base_len in the serialized form");
+
+        let result: Result<OperationSeq, _> = serde_json::from_str(&corrupted);
+        assert!(result.is_err());
+    }
+}